@@ -11,15 +11,93 @@
 //!
 //! The `ThreadKey` is a wrapper around `ThreadId`, but `!Send`. This allows one to certify that the current
 //! thread has the given `ThreadId`, without having to go through `thread::current().id()`.
+//!
+//! # [`Sticky`]
+//!
+//! `ThreadSafe` panics if it is dropped on a foreign thread, since running a thread-unsafe destructor off-thread
+//! isn't sound. [`Sticky`] is a sibling type with the same access API that instead parks the value in its origin
+//! thread's registry when dropped elsewhere, deferring the drop until that thread reclaims it.
 
 use std::{
+    any::Any,
+    cell::{Cell, RefCell},
     error::Error,
     fmt,
     marker::PhantomData,
     mem::{self, ManuallyDrop},
+    num::NonZeroUsize,
+    sync::atomic::{AtomicUsize, Ordering},
     thread::{self, ThreadId},
 };
 
+// Monotonically increasing counter handed out to threads, one per thread, to back
+// `ThreadToken`. Starts at 1 so that every issued token is nonzero.
+static THREAD_TOKEN_COUNTER: AtomicUsize = AtomicUsize::new(1);
+
+thread_local! {
+    // Lazily populated on first use by this thread, then just read out on every later call.
+    // Caching the `ThreadId` alongside the token avoids a second, separately-expensive call to
+    // `thread::current().id()` to service `ThreadKey::id()`.
+    static THIS_THREAD: Cell<Option<(ThreadToken, ThreadId)>> = const { Cell::new(None) };
+}
+
+/// A lightweight, cheaply comparable identifier for the current thread.
+///
+/// `thread::current().id()` clones an internal `Arc` on every call, which is wasteful for a
+/// check performed on every single [`ThreadSafe`] access. `ThreadToken` instead lazily assigns
+/// each thread a unique `NonZeroUsize` the first time it is asked, and caches it in a
+/// thread-local `Cell` afterwards, so the common path is a single non-atomic load.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+struct ThreadToken(NonZeroUsize);
+
+impl ThreadToken {
+    /// Hand out the next token value, panicking rather than wrapping if the counter is ever
+    /// exhausted. Mirrors the guard `std`'s own `ThreadId` allocator uses for the same reason.
+    fn next() -> NonZeroUsize {
+        let mut current = THREAD_TOKEN_COUNTER.load(Ordering::Relaxed);
+        loop {
+            let next = current
+                .checked_add(1)
+                .expect("failed to generate unique thread token: bitspace exhausted");
+            match THREAD_TOKEN_COUNTER.compare_exchange_weak(
+                current,
+                next,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                // SAFETY: the counter starts at 1 and `checked_add` above already proved
+                //         `current` didn't wrap to 0.
+                Ok(_) => return unsafe { NonZeroUsize::new_unchecked(current) },
+                Err(actual) => current = actual,
+            }
+        }
+    }
+
+    /// Get the token (and backing `ThreadId`) for the current thread, assigning one if this is
+    /// the first time the current thread has asked.
+    #[inline]
+    fn current_with_id() -> (ThreadToken, ThreadId) {
+        THIS_THREAD.with(|cell| {
+            if let Some(pair) = cell.get() {
+                return pair;
+            }
+
+            let id = thread::current().id();
+            let token = ThreadToken(Self::next());
+
+            let pair = (token, id);
+            cell.set(Some(pair));
+            pair
+        })
+    }
+
+    /// Get the token for the current thread, assigning one if needed.
+    #[inline]
+    fn current() -> ThreadToken {
+        Self::current_with_id().0
+    }
+}
+
 /// The whole point.
 ///
 /// This structure wraps around thread-unsafe data and only allows access if it comes from the thread that the
@@ -32,7 +110,7 @@ use std::{
 /// for the inner data is considered to be using it in a thread-unsafe context.
 pub struct ThreadSafe<T: ?Sized> {
     // thread that we originated in
-    origin_thread: ThreadId,
+    origin_token: ThreadToken,
     // whether or not we need to elide the drop check
     handle_drop: bool,
     // inner object
@@ -45,7 +123,7 @@ impl<T: Default> Default for ThreadSafe<T> {
         Self {
             inner: ManuallyDrop::new(T::default()),
             handle_drop: mem::needs_drop::<T>(),
-            origin_thread: thread::current().id(),
+            origin_token: ThreadToken::current(),
         }
     }
 }
@@ -53,7 +131,7 @@ impl<T: Default> Default for ThreadSafe<T> {
 impl<T: fmt::Debug + ?Sized> fmt::Debug for ThreadSafe<T> {
     #[inline]
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if self.origin_thread == thread::current().id() {
+        if self.origin_token == ThreadToken::current() {
             // SAFETY: self.inner can be accessed since we are on the origin thread
             fmt::Debug::fmt(&self.inner, f)
         } else {
@@ -71,7 +149,7 @@ impl<T> ThreadSafe<T> {
     #[inline]
     pub fn new(inner: T) -> ThreadSafe<T> {
         ThreadSafe {
-            origin_thread: thread::current().id(),
+            origin_token: ThreadToken::current(),
             handle_drop: mem::needs_drop::<T>(),
             inner: ManuallyDrop::new(inner),
         }
@@ -86,7 +164,7 @@ impl<T> ThreadSafe<T> {
     /// Attempt to convert to the inner type, using a thread key.
     #[inline]
     pub fn try_into_inner_with_key(mut self, key: ThreadKey) -> Result<T, ThreadSafe<T>> {
-        if self.origin_thread == key.id() {
+        if self.origin_token == key.token() {
             // SAFETY: "inner" can be used since we are in the origin thread
             //         we can take() because we delete the original right after
             let inner = unsafe { ManuallyDrop::take(&mut self.inner) };
@@ -115,6 +193,66 @@ impl<T> ThreadSafe<T> {
             Err(_) => panic!("Attempted to use a ThreadSafe outside of its origin thread"),
         }
     }
+
+    /// Transform the inner value, preserving the origin thread. This errors (returning the
+    /// original `ThreadSafe`) if it is not called in the origin thread.
+    #[inline]
+    pub fn try_map<U>(self, f: impl FnOnce(T) -> U) -> Result<ThreadSafe<U>, ThreadSafe<T>> {
+        self.try_map_with_key(ThreadKey::get(), f)
+    }
+
+    /// Transform the inner value, preserving the origin thread, using a thread key.
+    #[inline]
+    pub fn try_map_with_key<U>(
+        self,
+        key: ThreadKey,
+        f: impl FnOnce(T) -> U,
+    ) -> Result<ThreadSafe<U>, ThreadSafe<T>> {
+        let inner = self.try_into_inner_with_key(key)?;
+        Ok(ThreadSafe {
+            origin_token: key.token(),
+            handle_drop: mem::needs_drop::<U>(),
+            inner: ManuallyDrop::new(f(inner)),
+        })
+    }
+
+    /// Transform the inner value, preserving the origin thread. This panics if it is not called
+    /// in the origin thread.
+    #[inline]
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> ThreadSafe<U> {
+        match self.try_map(f) {
+            Ok(mapped) => mapped,
+            Err(_) => panic!("Attempted to use a ThreadSafe outside of its origin thread"),
+        }
+    }
+
+    /// Transform the inner value, preserving the origin thread, using a thread key.
+    #[inline]
+    pub fn map_with_key<U>(self, key: ThreadKey, f: impl FnOnce(T) -> U) -> ThreadSafe<U> {
+        match self.try_map_with_key(key, f) {
+            Ok(mapped) => mapped,
+            Err(_) => panic!("Attempted to use a ThreadSafe outside of its origin thread"),
+        }
+    }
+
+    /// Convert to the inner type without checking that we are in the origin thread.
+    ///
+    /// # Safety
+    ///
+    /// The caller must independently guarantee that it is sound to move `T` out right now —
+    /// typically because external invariants already establish that this is the origin thread
+    /// (analogous to how `Mutex::into_inner` can skip locking because it statically has no
+    /// outstanding references). Calling this from a foreign thread when `T` is not actually safe
+    /// to move is undefined behavior.
+    #[inline]
+    pub unsafe fn into_inner_unchecked(mut self) -> T {
+        // SAFETY: the caller guarantees this is sound; we can take() because we delete the
+        //         original right after
+        let inner = ManuallyDrop::take(&mut self.inner);
+        // SAFETY: suppress the dropper on this object
+        mem::forget(self);
+        inner
+    }
 }
 
 impl<T: ?Sized> ThreadSafe<T> {
@@ -127,7 +265,7 @@ impl<T: ?Sized> ThreadSafe<T> {
     /// Try to get a reference to the inner type, using a thread key.
     #[inline]
     pub fn try_get_ref_with_key(&self, key: ThreadKey) -> Result<&T, NotInOriginThread> {
-        if self.origin_thread == key.id() {
+        if self.origin_token == key.token() {
             // SAFETY: "inner" can be used since we are in the origin thread
             //         it is unlikely that &T can be sent to another thread
             Ok(&self.inner)
@@ -167,7 +305,7 @@ impl<T: ?Sized> ThreadSafe<T> {
     /// Try to get a mutable reference to the inner type, using a thread key.
     #[inline]
     pub fn try_get_mut_with_key(&mut self, key: ThreadKey) -> Result<&mut T, NotInOriginThread> {
-        if self.origin_thread == key.id() {
+        if self.origin_token == key.token() {
             // SAFETY: "inner" can be used since we are in the origin thread
             //         it is unlikely that &mut T can be sent to another thread
             Ok(&mut self.inner)
@@ -197,6 +335,29 @@ impl<T: ?Sized> ThreadSafe<T> {
             }
         }
     }
+
+    /// Get a reference to the inner type without checking that we are in the origin thread.
+    ///
+    /// # Safety
+    ///
+    /// The caller must independently guarantee that it is sound to access `T` right now —
+    /// typically because external invariants already establish that this is the origin thread.
+    #[inline]
+    pub unsafe fn get_ref_unchecked(&self) -> &T {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the inner type without checking that we are in the origin
+    /// thread.
+    ///
+    /// # Safety
+    ///
+    /// The caller must independently guarantee that it is sound to access `T` right now —
+    /// typically because external invariants already establish that this is the origin thread.
+    #[inline]
+    pub unsafe fn get_mut_unchecked(&mut self) -> &mut T {
+        &mut self.inner
+    }
 }
 
 impl<T: Clone> ThreadSafe<T> {
@@ -213,7 +374,7 @@ impl<T: Clone> ThreadSafe<T> {
             Ok(r) => Ok(ThreadSafe {
                 inner: ManuallyDrop::new(r.clone()),
                 handle_drop: self.handle_drop,
-                origin_thread: self.origin_thread,
+                origin_token: self.origin_token,
             }),
             Err(NotInOriginThread) => Err(NotInOriginThread),
         }
@@ -225,7 +386,7 @@ impl<T: Clone> ThreadSafe<T> {
         ThreadSafe {
             inner: ManuallyDrop::new(self.get_ref_with_key(key).clone()),
             handle_drop: self.handle_drop,
-            origin_thread: self.origin_thread,
+            origin_token: self.origin_token,
         }
     }
 }
@@ -242,7 +403,7 @@ impl<T: ?Sized> Drop for ThreadSafe<T> {
     #[inline]
     fn drop(&mut self) {
         // SAFETY: handle_drop is only turned on if the internal type is needs_drop() in some way
-        if self.handle_drop && self.origin_thread != thread::current().id() {
+        if self.handle_drop && self.origin_token != ThreadToken::current() {
             // SAFETY: we cannot allow the type to be dropped, as this is thread unsafe
             panic!("Attempted to drop ThreadSafe<_> outside of its origin thread");
         } else {
@@ -256,6 +417,7 @@ impl<T: ?Sized> Drop for ThreadSafe<T> {
 /// A `ThreadId` that is guaranteed to refer to the current thread, since this is `!Send`.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct ThreadKey {
+    token: ThreadToken,
     id: ThreadId,
     // ensure this is !Send and !Sync
     _phantom: PhantomData<*const ThreadId>,
@@ -270,10 +432,15 @@ impl Default for ThreadKey {
 
 impl ThreadKey {
     /// Create a new `ThreadKey` based on the current thread.
+    ///
+    /// On the common path (after the first call on a given thread) this is a thread-local read
+    /// with no allocation or atomic operation involved.
     #[inline]
     pub fn get() -> Self {
+        let (token, id) = ThreadToken::current_with_id();
         Self {
-            id: thread::current().id(),
+            token,
+            id,
             _phantom: PhantomData,
         }
     }
@@ -286,6 +453,9 @@ impl ThreadKey {
     #[inline]
     pub unsafe fn new(id: ThreadId) -> Self {
         Self {
+            // The caller guarantees this key is only used on the thread that `id` refers to, so
+            // the current thread's token is the right one to pair it with.
+            token: ThreadToken::current(),
             id,
             _phantom: PhantomData,
         }
@@ -296,6 +466,12 @@ impl ThreadKey {
     pub fn id(self) -> ThreadId {
         self.id
     }
+
+    /// Get the lightweight thread token backing this `ThreadKey`.
+    #[inline]
+    fn token(self) -> ThreadToken {
+        self.token
+    }
 }
 
 impl From<ThreadKey> for ThreadId {
@@ -317,3 +493,238 @@ impl fmt::Display for NotInOriginThread {
 }
 
 impl Error for NotInOriginThread {}
+
+thread_local! {
+    // Per-thread slab of parked values. A `None` entry is a free slot that can be reused by
+    // the next `Sticky::new` call on this thread. When a thread exits, this thread-local is
+    // torn down and every remaining `Some` slot is dropped along with it, which is what
+    // guarantees that parked values never outlive their origin thread.
+    static STICKY_REGISTRY: RefCell<Vec<Option<Box<dyn Any>>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Like [`ThreadSafe`], but dropping it on a foreign thread never panics.
+///
+/// Instead of running `T`'s destructor off-thread (which `ThreadSafe` refuses to do), a
+/// `Sticky<T>` parks the value in a registry local to its origin thread. Dropping the
+/// `Sticky` on a foreign thread just leaves the value parked there; it is reclaimed, along
+/// with every other value still parked on that thread, when the origin thread itself exits.
+/// Dropping it on the origin thread removes and drops the value immediately, same as
+/// `ThreadSafe`.
+///
+/// This makes `Sticky<T>` safe to store in data structures whose teardown order you don't
+/// control, at the cost of the value potentially outliving the `Sticky` handle that named it.
+/// That cost can be substantial, not just "a little longer": a value dropped on a foreign
+/// thread stays parked, and its slot is never reused, until the *origin* thread exits. For a
+/// long-lived origin thread (a GUI main thread is the prototypical case, e.g. the
+/// `Window`/`Surface` use case this type exists for) that means repeatedly creating and
+/// foreign-dropping `Sticky<T>` values grows that thread's registry for the life of the
+/// program. Only values dropped on the origin thread free their slot for reuse.
+pub struct Sticky<T: 'static> {
+    // thread that we originated in
+    origin_token: ThreadToken,
+    // index into that thread's `STICKY_REGISTRY`
+    slot_id: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+
+// SAFETY: a `Sticky` only ever stores a `ThreadToken` and an index into a thread-local registry;
+// the actual `T` never leaves its origin thread, so the handle itself is trivially shareable.
+unsafe impl<T: 'static> Send for Sticky<T> {}
+unsafe impl<T: 'static> Sync for Sticky<T> {}
+
+impl<T: 'static> Sticky<T> {
+    /// Create a new instance of a `Sticky`, parking `inner` in this thread's registry.
+    #[inline]
+    pub fn new(inner: T) -> Sticky<T> {
+        let boxed: Box<dyn Any> = Box::new(inner);
+        let slot_id = STICKY_REGISTRY.with(|registry| {
+            let mut registry = registry.borrow_mut();
+            match registry.iter().position(Option::is_none) {
+                Some(slot_id) => {
+                    registry[slot_id] = Some(boxed);
+                    slot_id
+                }
+                None => {
+                    registry.push(Some(boxed));
+                    registry.len() - 1
+                }
+            }
+        });
+
+        Sticky {
+            origin_token: ThreadToken::current(),
+            slot_id,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Attempt to convert to the inner type. This errors if it is not in the origin thread.
+    #[inline]
+    pub fn try_into_inner(self) -> Result<T, Sticky<T>> {
+        self.try_into_inner_with_key(ThreadKey::get())
+    }
+
+    /// Attempt to convert to the inner type, using a thread key.
+    #[inline]
+    pub fn try_into_inner_with_key(self, key: ThreadKey) -> Result<T, Sticky<T>> {
+        if self.origin_token != key.token() {
+            return Err(self);
+        }
+
+        // SAFETY: we are on the origin thread, so this slot is ours to take. It is still
+        // `Some` because only the origin thread ever removes a slot, and we are it.
+        let boxed = STICKY_REGISTRY
+            .with(|registry| registry.borrow_mut()[self.slot_id].take())
+            .expect("Sticky slot was already empty on its origin thread");
+        // SAFETY: suppress the dropper on this object; the slot has already been reclaimed
+        mem::forget(self);
+        Ok(*boxed
+            .downcast::<T>()
+            .unwrap_or_else(|_| unreachable!("Sticky slot held the wrong type")))
+    }
+
+    /// Attempt to convert to the inner type. This panics if it is not in the origin thread.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        match self.try_into_inner() {
+            Ok(i) => i,
+            Err(_) => panic!("Attempted to use a Sticky outside of its origin thread"),
+        }
+    }
+
+    /// Attempt to convert to the inner type, using a thread key.
+    #[inline]
+    pub fn into_inner_with_key(self, key: ThreadKey) -> T {
+        match self.try_into_inner_with_key(key) {
+            Ok(i) => i,
+            Err(_) => panic!("Attempted to use a Sticky outside of its origin thread"),
+        }
+    }
+
+    /// Try to get a reference to the inner type. This errors if it is not in the origin thread.
+    #[inline]
+    pub fn try_get_ref(&self) -> Result<&T, NotInOriginThread> {
+        self.try_get_ref_with_key(ThreadKey::get())
+    }
+
+    /// Try to get a reference to the inner type, using a thread key.
+    #[inline]
+    pub fn try_get_ref_with_key(&self, key: ThreadKey) -> Result<&T, NotInOriginThread> {
+        if self.origin_token != key.token() {
+            return Err(NotInOriginThread);
+        }
+
+        STICKY_REGISTRY.with(|registry| {
+            let registry = registry.borrow();
+            let slot = registry[self.slot_id]
+                .as_ref()
+                .expect("Sticky slot was already empty on its origin thread");
+            let value = slot
+                .downcast_ref::<T>()
+                .unwrap_or_else(|| unreachable!("Sticky slot held the wrong type"));
+            // SAFETY: `value` is heap-allocated behind the `Box<dyn Any>`, so its address is
+            // stable even if `registry`'s backing `Vec` reallocates. The slot can only be
+            // removed from the origin thread, and we hold `&self` for at least as long as the
+            // returned reference, so it cannot be removed out from under us.
+            Ok(unsafe { &*(value as *const T) })
+        })
+    }
+
+    /// Get a reference to the inner type. This panics if it is not called in the origin thread.
+    #[inline]
+    pub fn get_ref(&self) -> &T {
+        match self.try_get_ref() {
+            Ok(i) => i,
+            Err(NotInOriginThread) => {
+                panic!("Attempted to use a Sticky outside of its origin thread")
+            }
+        }
+    }
+
+    /// Get a reference to the inner type, using a thread key.
+    #[inline]
+    pub fn get_ref_with_key(&self, key: ThreadKey) -> &T {
+        match self.try_get_ref_with_key(key) {
+            Ok(i) => i,
+            Err(NotInOriginThread) => {
+                panic!("Attempted to use a Sticky outside of its origin thread")
+            }
+        }
+    }
+
+    /// Try to get a mutable reference to the inner type. This errors if it is not in the
+    /// origin thread.
+    #[inline]
+    pub fn try_get_mut(&mut self) -> Result<&mut T, NotInOriginThread> {
+        self.try_get_mut_with_key(ThreadKey::get())
+    }
+
+    /// Try to get a mutable reference to the inner type, using a thread key.
+    #[inline]
+    pub fn try_get_mut_with_key(&mut self, key: ThreadKey) -> Result<&mut T, NotInOriginThread> {
+        if self.origin_token != key.token() {
+            return Err(NotInOriginThread);
+        }
+
+        STICKY_REGISTRY.with(|registry| {
+            let mut registry = registry.borrow_mut();
+            let slot = registry[self.slot_id]
+                .as_mut()
+                .expect("Sticky slot was already empty on its origin thread");
+            let value = slot
+                .downcast_mut::<T>()
+                .unwrap_or_else(|| unreachable!("Sticky slot held the wrong type"));
+            // SAFETY: same reasoning as `try_get_ref_with_key`, but for a unique borrow; we
+            // hold `&mut self`, so no other `Sticky` for this slot can race us.
+            Ok(unsafe { &mut *(value as *mut T) })
+        })
+    }
+
+    /// Get a mutable reference to the inner type. This panics if it is not called in the
+    /// origin thread.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        match self.try_get_mut() {
+            Ok(i) => i,
+            Err(NotInOriginThread) => {
+                panic!("Attempted to use a Sticky outside of its origin thread")
+            }
+        }
+    }
+
+    /// Get a mutable reference to the inner type, using a thread key.
+    #[inline]
+    pub fn get_mut_with_key(&mut self, key: ThreadKey) -> &mut T {
+        match self.try_get_mut_with_key(key) {
+            Ok(i) => i,
+            Err(NotInOriginThread) => {
+                panic!("Attempted to use a Sticky outside of its origin thread")
+            }
+        }
+    }
+}
+
+impl<T: fmt::Debug + 'static> fmt::Debug for Sticky<T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.try_get_ref() {
+            Ok(inner) => fmt::Debug::fmt(inner, f),
+            Err(NotInOriginThread) => f.write_str("<not in origin thread>"),
+        }
+    }
+}
+
+impl<T: 'static> Drop for Sticky<T> {
+    #[inline]
+    fn drop(&mut self) {
+        if self.origin_token == ThreadToken::current() {
+            // SAFETY: we are on the origin thread, so it is fine to drop the parked value
+            // right now.
+            STICKY_REGISTRY.with(|registry| {
+                registry.borrow_mut()[self.slot_id].take();
+            });
+        }
+        // Otherwise, leave the value parked in the origin thread's registry. It will be
+        // dropped when the origin thread exits and `STICKY_REGISTRY` itself is torn down.
+    }
+}